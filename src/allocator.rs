@@ -0,0 +1,35 @@
+/// FFI-safe handle to an allocator, so a boxed lambda's backing storage can be
+/// allocated and freed on the same side of the FFI boundary regardless of which
+/// library ends up running its `Drop`.
+///
+/// Both function pointers follow the [`std::alloc::GlobalAlloc`] convention of
+/// `size`/`align` taken from a [`std::alloc::Layout`].
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
+#[derive(Clone, Copy)]
+pub struct RAllocator {
+    pub alloc: extern "C" fn(size: usize, align: usize) -> *mut u8,
+    pub dealloc: extern "C" fn(ptr: *mut u8, size: usize, align: usize),
+}
+
+impl RAllocator {
+    extern "C" fn global_alloc(size: usize, align: usize) -> *mut u8 {
+        unsafe { std::alloc::alloc(std::alloc::Layout::from_size_align_unchecked(size, align)) }
+    }
+
+    extern "C" fn global_dealloc(ptr: *mut u8, size: usize, align: usize) {
+        unsafe {
+            std::alloc::dealloc(
+                ptr,
+                std::alloc::Layout::from_size_align_unchecked(size, align),
+            )
+        }
+    }
+
+    /// An [`RAllocator`] backed by the Rust global allocator, equivalent to what
+    /// `Box::new`/`Box::from_raw` already use internally.
+    pub const GLOBAL: RAllocator = RAllocator {
+        alloc: Self::global_alloc,
+        dealloc: Self::global_dealloc,
+    };
+}
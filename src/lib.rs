@@ -1,7 +1,15 @@
+mod allocator;
 mod rfn;
 mod rfnmut;
 mod rfnonce;
+mod rinline;
+mod rpanic;
 
-pub use rfn::{RFn, RRefFn, RBoxFn};
-pub use rfnmut::{RFnMut, RRefFnMut, RBoxFnMut};
-pub use rfnonce::{RBoxFnOnce, RFnOnce};
\ No newline at end of file
+pub use allocator::RAllocator;
+pub use rfn::{
+    RFn, RRefFn, RRefFnSend, RRefFnSync, RRefFnTry, RBoxFn, RBoxFnSend, RBoxFnSync, RBoxFnTry,
+};
+pub use rfnmut::{RFnMut, RRefFnMut, RRefFnMutSend, RRefFnMutTry, RBoxFnMut, RBoxFnMutSend, RBoxFnMutTry};
+pub use rfnonce::{RBoxFnOnce, RBoxFnOnceSend, RBoxFnOnceTry, RFnOnce};
+pub use rinline::RInlineFn;
+pub use rpanic::{RPanicInfo, RResult};
\ No newline at end of file
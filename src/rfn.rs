@@ -1,4 +1,8 @@
 use std::marker::PhantomData;
+use std::panic::AssertUnwindSafe;
+
+use crate::allocator::RAllocator;
+use crate::rpanic::{RPanicInfo, RResult};
 
 pub trait RFn<'a, TParam, TResult: 'a> {
     fn call(&'a self, p: TParam) -> TResult;
@@ -17,6 +21,12 @@ pub struct RRefFn<'a, TParam, TResult> {
     ptr: extern "C" fn(usize, TParam) -> TResult,
     inner: usize,
     p: PhantomData<&'a ()>,
+    // The erased `inner`/`ptr` fields are all trivially `Send`/`Sync` on their own,
+    // which would otherwise let this type auto-implement both regardless of the
+    // captured closure. `*const ()` is neither, so this field suppresses the
+    // auto-impls; `Send`/`Sync` are re-granted only through the bounded
+    // `RRefFnSend`/`RRefFnSync` wrappers below.
+    _not_send_sync: PhantomData<*const ()>,
 }
 
 impl<'a, T, TParam, TResult> From<&'a T> for RRefFn<'a, TParam, TResult>
@@ -37,6 +47,7 @@ where
             ptr: ptr::<T, TParam, TResult>,
             inner,
             p: PhantomData,
+            _not_send_sync: PhantomData,
         }
     }
 }
@@ -51,8 +62,12 @@ impl<'a, TParam, TResult: 'a> RFn<'a, TParam, TResult> for RRefFn<'a, TParam, TR
 #[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
 pub struct RBoxFn<TParam, TResult> {
     caller: extern "C" fn(usize, TParam) -> TResult,
-    remover: extern "C" fn(usize),
-    inner: usize
+    remover: extern "C" fn(usize, RAllocator),
+    inner: usize,
+    allocator: RAllocator,
+    // See `RRefFn::_not_send_sync`: suppresses accidental auto-`Send`/`Sync` leaking
+    // through the erased `usize`/fn-pointer fields regardless of the captured closure.
+    _not_send_sync: PhantomData<*const ()>,
 }
 
 impl<TFn, TParam, TResult> From<TFn> for RBoxFn<TParam, TResult>
@@ -60,15 +75,219 @@ where
     TFn: Fn(TParam) -> TResult,
 {
     fn from(inner: TFn) -> Self {
+        Self::from_in(inner, RAllocator::GLOBAL)
+    }
+}
+
+extern "C" fn caller<TFn, TParam, TResult>(that: usize, param: TParam) -> TResult
+where
+    TFn: Fn(TParam) -> TResult,
+{
+    (unsafe { &*(that as *mut TFn) })(param)
+}
+
+extern "C" fn dropper<TFn, TParam, TResult>(that: usize, allocator: RAllocator)
+where
+    TFn: Fn(TParam) -> TResult,
+{
+    let ptr = that as *mut TFn;
+    unsafe {
+        std::ptr::drop_in_place(ptr);
+        // `GlobalAlloc`'s contract forbids a zero-sized layout; a ZST (e.g. a
+        // non-capturing closure) never went through `allocator.alloc` in the first
+        // place (see `from_in`), so it must not go through `allocator.dealloc` either.
+        let layout = std::alloc::Layout::new::<TFn>();
+        if layout.size() != 0 {
+            (allocator.dealloc)(ptr as *mut u8, layout.size(), layout.align());
+        }
+    }
+}
+
+impl<TParam, TResult> RBoxFn<TParam, TResult> {
+    /// Builds an [`RBoxFn`] whose backing storage is allocated through `allocator`
+    /// instead of the global allocator, so allocation and deallocation always happen
+    /// on the same side of the FFI boundary regardless of which library runs `Drop`.
+    pub fn from_in<TFn>(inner: TFn, allocator: RAllocator) -> Self
+    where
+        TFn: Fn(TParam) -> TResult,
+    {
+        let layout = std::alloc::Layout::new::<TFn>();
+        let ptr = if layout.size() == 0 {
+            std::ptr::NonNull::<TFn>::dangling().as_ptr()
+        } else {
+            let ptr = (allocator.alloc)(layout.size(), layout.align()) as *mut TFn;
+            assert!(!ptr.is_null(), "RAllocator::alloc returned a null pointer");
+            ptr
+        };
+        unsafe { ptr.write(inner) };
+
+        Self {
+            caller: caller::<TFn, TParam, TResult>,
+            remover: dropper::<TFn, TParam, TResult>,
+            inner: ptr as usize,
+            allocator,
+            _not_send_sync: PhantomData,
+        }
+    }
+}
+
+impl<'a, TParam, TResult> Drop for RBoxFn<TParam, TResult> {
+    fn drop(&mut self) {
+        (self.remover)(self.inner, self.allocator);
+    }
+}
+
+impl<'a, TParam, TResult: 'a> RFn<'a, TParam, TResult> for RBoxFn<TParam, TResult> {
+    fn call(&self, p: TParam) -> TResult {
+        (self.caller)(self.inner, p)
+    }
+}
+
+/// Thread-safe variant of [`RBoxFn`] for closures that may be sent to another thread,
+/// e.g. handed off to a thread pool or [`std::thread::spawn`]. Captures the `TFn: Send`
+/// bound at construction time, mirroring how `Arc<T>` only opts into `Send` when `T: Send`.
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
+pub struct RBoxFnSend<TParam, TResult>(RBoxFn<TParam, TResult>);
+
+impl<TFn, TParam, TResult> From<TFn> for RBoxFnSend<TParam, TResult>
+where
+    TFn: Fn(TParam) -> TResult + Send,
+{
+    fn from(inner: TFn) -> Self {
+        Self(inner.into())
+    }
+}
+
+unsafe impl<TParam, TResult> Send for RBoxFnSend<TParam, TResult> {}
+
+impl<'a, TParam, TResult: 'a> RFn<'a, TParam, TResult> for RBoxFnSend<TParam, TResult> {
+    fn call(&'a self, p: TParam) -> TResult {
+        self.0.call(p)
+    }
+}
+
+/// Thread-safe variant of [`RBoxFn`] for closures that may additionally be shared
+/// between threads behind a reference (e.g. wrapped in an `Arc`). Requires
+/// `TFn: Send + Sync`, the same bound `Arc<T>` requires before opting into both.
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
+pub struct RBoxFnSync<TParam, TResult>(RBoxFn<TParam, TResult>);
+
+impl<TFn, TParam, TResult> From<TFn> for RBoxFnSync<TParam, TResult>
+where
+    TFn: Fn(TParam) -> TResult + Send + Sync,
+{
+    fn from(inner: TFn) -> Self {
+        Self(inner.into())
+    }
+}
+
+unsafe impl<TParam, TResult> Send for RBoxFnSync<TParam, TResult> {}
+unsafe impl<TParam, TResult> Sync for RBoxFnSync<TParam, TResult> {}
+
+impl<'a, TParam, TResult: 'a> RFn<'a, TParam, TResult> for RBoxFnSync<TParam, TResult> {
+    fn call(&'a self, p: TParam) -> TResult {
+        self.0.call(p)
+    }
+}
+
+/// Thread-safe variant of [`RRefFn`] for closures borrowed from a context that may be
+/// sent to another thread. Since only a shared reference is held, both `Send` and
+/// `Sync` only require the closure to be `Sync`, matching `&T`'s own `Send`/`Sync`
+/// rules — so this type is actually already `Sync` too; see [`RRefFnSync`], which
+/// exists only for naming symmetry with [`RBoxFnSend`]/[`RBoxFnSync`].
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
+pub struct RRefFnSend<'a, TParam, TResult>(RRefFn<'a, TParam, TResult>);
+
+impl<'a, T, TParam, TResult> From<&'a T> for RRefFnSend<'a, TParam, TResult>
+where
+    T: 'a + Fn(TParam) -> TResult + Sync,
+{
+    fn from(inner: &'a T) -> Self {
+        Self(inner.into())
+    }
+}
+
+unsafe impl<'a, TParam, TResult> Send for RRefFnSend<'a, TParam, TResult> {}
+unsafe impl<'a, TParam, TResult> Sync for RRefFnSend<'a, TParam, TResult> {}
+
+impl<'a, TParam, TResult: 'a> RFn<'a, TParam, TResult> for RRefFnSend<'a, TParam, TResult> {
+    fn call(&'a self, p: TParam) -> TResult {
+        self.0.call(p)
+    }
+}
+
+/// Thread-safe variant of [`RRefFn`] for closures borrowed from a context that may
+/// additionally be shared between threads behind a reference.
+///
+/// For a borrowed closure, `Send` and `Sync` both reduce to the same `T: Sync` bound
+/// (see [`RRefFnSend`]), so this type is sound but not strictly necessary; it is kept
+/// as a distinct name so the `RRefFn*`/`RBoxFn*` families read the same way at call
+/// sites rather than requiring callers to remember that only the `Ref` family
+/// collapses the two bounds.
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
+pub struct RRefFnSync<'a, TParam, TResult>(RRefFn<'a, TParam, TResult>);
+
+impl<'a, T, TParam, TResult> From<&'a T> for RRefFnSync<'a, TParam, TResult>
+where
+    T: 'a + Fn(TParam) -> TResult + Sync,
+{
+    fn from(inner: &'a T) -> Self {
+        Self(inner.into())
+    }
+}
+
+unsafe impl<'a, TParam, TResult> Send for RRefFnSync<'a, TParam, TResult> {}
+unsafe impl<'a, TParam, TResult> Sync for RRefFnSync<'a, TParam, TResult> {}
+
+impl<'a, TParam, TResult: 'a> RFn<'a, TParam, TResult> for RRefFnSync<'a, TParam, TResult> {
+    fn call(&'a self, p: TParam) -> TResult {
+        self.0.call(p)
+    }
+}
+
+/// Panic-safe variant of [`RBoxFn`] whose trampoline catches any panic raised by the
+/// closure instead of letting it unwind across the `extern "C"` boundary, which is
+/// undefined behavior.
+///
+/// [`RFn::call`] returns `RResult::ROk` on a normal return and `RResult::RErr` with an
+/// [`RPanicInfo`] if the closure panicked.
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
+pub struct RBoxFnTry<TParam, TResult> {
+    caller: extern "C" fn(usize, TParam) -> RResult<TResult, RPanicInfo>,
+    remover: extern "C" fn(usize),
+    inner: usize,
+}
+
+impl<TFn, TParam, TResult> From<TFn> for RBoxFnTry<TParam, TResult>
+where
+    TFn: Fn(TParam) -> TResult,
+{
+    fn from(inner: TFn) -> Self {
+        RPanicInfo::install_hook();
         let box_inner = Box::new(inner);
-        let inner = Box::into_raw(box_inner);
-        let inner = inner as usize;
+        let inner = Box::into_raw(box_inner) as usize;
 
-        extern "C" fn caller<TFn, TParam, TResult>(that: usize, param: TParam) -> TResult
+        // `RPanicInfo::message` is a plain `String` outside the `abi_stable` feature,
+        // which isn't FFI-safe; that's intentional here, since this trampoline only
+        // crosses the `catch_unwind` boundary within a single compilation, not an
+        // actual dylib seam, unlike the rest of this crate's `extern "C"` surface.
+        #[allow(improper_ctypes_definitions)]
+        extern "C" fn caller<TFn, TParam, TResult>(
+            that: usize,
+            param: TParam,
+        ) -> RResult<TResult, RPanicInfo>
         where
             TFn: Fn(TParam) -> TResult,
         {
-            (unsafe { &*(that as *mut TFn) })(param)
+            let function = unsafe { &*(that as *mut TFn) };
+            std::panic::catch_unwind(AssertUnwindSafe(|| function(param)))
+                .map_err(RPanicInfo::from_payload)
+                .into()
         }
         extern "C" fn dropper<TFn, TParam, TResult>(that: usize)
         where
@@ -84,18 +303,113 @@ where
     }
 }
 
-impl<'a, TParam, TResult> Drop for RBoxFn<TParam, TResult> {
+impl<TParam, TResult> Drop for RBoxFnTry<TParam, TResult> {
     fn drop(&mut self) {
         (self.remover)(self.inner);
     }
 }
 
-impl<'a, TParam, TResult: 'a> RFn<'a, TParam, TResult> for RBoxFn<TParam, TResult> {
-    fn call(&self, p: TParam) -> TResult {
+impl<'a, TParam, TResult: 'a> RFn<'a, TParam, RResult<TResult, RPanicInfo>>
+    for RBoxFnTry<TParam, TResult>
+{
+    fn call(&'a self, p: TParam) -> RResult<TResult, RPanicInfo> {
         (self.caller)(self.inner, p)
     }
 }
 
+/// Panic-safe variant of [`RRefFn`] whose trampoline catches any panic raised by the
+/// closure instead of letting it unwind across the `extern "C"` boundary.
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
+pub struct RRefFnTry<'a, TParam, TResult> {
+    ptr: extern "C" fn(usize, TParam) -> RResult<TResult, RPanicInfo>,
+    inner: usize,
+    p: PhantomData<&'a ()>,
+}
+
+impl<'a, T, TParam, TResult> From<&'a T> for RRefFnTry<'a, TParam, TResult>
+where
+    T: 'a + Fn(TParam) -> TResult,
+{
+    fn from(inner: &'a T) -> Self {
+        RPanicInfo::install_hook();
+        let inner: usize = unsafe { std::mem::transmute(inner) };
+
+        extern "C" fn ptr<T, TParam, TResult>(
+            inner: usize,
+            p: TParam,
+        ) -> RResult<TResult, RPanicInfo>
+        where
+            T: Fn(TParam) -> TResult,
+        {
+            let function: &T = unsafe { std::mem::transmute(inner) };
+            std::panic::catch_unwind(AssertUnwindSafe(|| function(p)))
+                .map_err(RPanicInfo::from_payload)
+                .into()
+        }
+        Self {
+            ptr: ptr::<T, TParam, TResult>,
+            inner,
+            p: PhantomData,
+        }
+    }
+}
+
+impl<'a, TParam, TResult: 'a> RFn<'a, TParam, RResult<TResult, RPanicInfo>>
+    for RRefFnTry<'a, TParam, TResult>
+{
+    fn call(&'a self, p: TParam) -> RResult<TResult, RPanicInfo> {
+        (self.ptr)(self.inner, p)
+    }
+}
+
+// `impl<TFn, TParam, TResult> From<TFn> for RBoxFn<TParam, TResult>` above already
+// covers the 1-argument case (TParam is simply the closure's one parameter type), so
+// these only need to fill in 0 and 2..=16: each tuples the arguments and hands an
+// adapter closure to that existing `From` impl rather than duplicating the boxing
+// logic. Each arity gets its own method name (`from_fn0`, `from_fn2`, ...) rather than
+// one overloaded `from_fn`, since an inherent method of the same name repeated across
+// impls on different tuple shapes is ambiguous to rustc even when the call site's
+// target type is fully known. `RRefFn` is intentionally left out: it is a
+// zero-allocation wrapper around a borrowed closure, and there's nowhere to stash the
+// tupling adapter without boxing it.
+macro_rules! impl_rbox_fn_arity {
+    ($($name:ident => ($($arg:ident),*)),+ $(,)?) => {
+        $(
+            impl<$($arg,)* TResult> RBoxFn<($($arg,)*), TResult> {
+                /// Builds an [`RBoxFn`] from an ordinary closure of this arity,
+                /// tupling its arguments at the FFI seam.
+                pub fn $name<TFn>(inner: TFn) -> Self
+                where
+                    TFn: Fn($($arg),*) -> TResult,
+                {
+                    #[allow(non_snake_case)]
+                    RBoxFn::from(move |($($arg,)*): ($($arg,)*)| inner($($arg),*))
+                }
+            }
+        )+
+    };
+}
+
+impl_rbox_fn_arity!(
+    from_fn0 => (),
+    from_fn2 => (A, B),
+    from_fn3 => (A, B, C),
+    from_fn4 => (A, B, C, D),
+    from_fn5 => (A, B, C, D, E),
+    from_fn6 => (A, B, C, D, E, F),
+    from_fn7 => (A, B, C, D, E, F, G),
+    from_fn8 => (A, B, C, D, E, F, G, H),
+    from_fn9 => (A, B, C, D, E, F, G, H, I),
+    from_fn10 => (A, B, C, D, E, F, G, H, I, J),
+    from_fn11 => (A, B, C, D, E, F, G, H, I, J, K),
+    from_fn12 => (A, B, C, D, E, F, G, H, I, J, K, L),
+    from_fn13 => (A, B, C, D, E, F, G, H, I, J, K, L, M),
+    from_fn14 => (A, B, C, D, E, F, G, H, I, J, K, L, M, N),
+    from_fn15 => (A, B, C, D, E, F, G, H, I, J, K, L, M, N, O),
+    from_fn16 => (A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P),
+);
+
 #[cfg(test)]
 mod tests {
     use std::sync::atomic::{AtomicU8, Ordering};
@@ -161,4 +475,102 @@ mod tests {
         drop(closure);
         assert_eq!(42, DROP_EVENT.load(Ordering::SeqCst));
     }
+
+    #[test]
+    fn box_fn_send_across_thread() {
+        let owned = "Value".to_owned();
+        let fun: RBoxFnSend<(), usize> = (move |_| owned.len()).into();
+        let result = std::thread::spawn(move || fun.call(())).join().unwrap();
+        assert_eq!(5, result);
+    }
+
+    #[test]
+    fn box_fn_sync_shared_across_threads() {
+        let fun: std::sync::Arc<RBoxFnSync<(), usize>> =
+            std::sync::Arc::new((|_| 5usize).into());
+        let a = std::sync::Arc::clone(&fun);
+        let result = std::thread::spawn(move || a.call(())).join().unwrap();
+        assert_eq!(5, result);
+        assert_eq!(5, fun.call(()));
+    }
+
+    #[test]
+    fn box_fn_from_in_uses_given_allocator() {
+        static ALLOC_COUNT: AtomicU8 = AtomicU8::new(0);
+        static DEALLOC_COUNT: AtomicU8 = AtomicU8::new(0);
+
+        extern "C" fn alloc(size: usize, align: usize) -> *mut u8 {
+            ALLOC_COUNT.fetch_add(1, Ordering::SeqCst);
+            unsafe { std::alloc::alloc(std::alloc::Layout::from_size_align_unchecked(size, align)) }
+        }
+        extern "C" fn dealloc(ptr: *mut u8, size: usize, align: usize) {
+            DEALLOC_COUNT.fetch_add(1, Ordering::SeqCst);
+            unsafe {
+                std::alloc::dealloc(ptr, std::alloc::Layout::from_size_align_unchecked(size, align))
+            }
+        }
+
+        let owned = "Value".to_owned();
+        let fun = RBoxFn::from_in(move |_: ()| owned.len(), RAllocator { alloc, dealloc });
+        assert_eq!(1, ALLOC_COUNT.load(Ordering::SeqCst));
+        assert_eq!(5, fun.call(()));
+        drop(fun);
+        assert_eq!(1, DEALLOC_COUNT.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn box_fn_from_fn_arity_zero() {
+        let fun: RBoxFn<(), usize> = RBoxFn::from_fn0(|| 5usize);
+        assert_eq!(5, fun.call(()));
+    }
+
+    #[test]
+    fn box_fn_from_fn_arity_two() {
+        let fun: RBoxFn<(usize, usize), usize> = RBoxFn::from_fn2(|a: usize, b: usize| a + b);
+        assert_eq!(5, fun.call((2, 3)));
+    }
+
+    #[test]
+    fn box_fn_from_fn_arity_three_captures() {
+        let suffix = "!".to_owned();
+        let fun: RBoxFn<(&str, &str, &str), String> =
+            RBoxFn::from_fn3(move |a: &str, b: &str, c: &str| format!("{a}{b}{c}{suffix}"));
+        assert_eq!("abc!", fun.call(("a", "b", "c")));
+    }
+
+    #[test]
+    fn box_fn_try_returns_ok_on_success() {
+        let fun: RBoxFnTry<(), usize> = (|_| 5usize).into();
+        match fun.call(()) {
+            RResult::ROk(v) => assert_eq!(5, v),
+            RResult::RErr(_) => panic!("expected ROk"),
+        }
+    }
+
+    #[test]
+    fn box_fn_try_catches_panic() {
+        let fun: RBoxFnTry<(), usize> = (|_| panic!("boom")).into();
+        match fun.call(()) {
+            RResult::ROk(_) => panic!("expected RErr"),
+            RResult::RErr(info) => assert_eq!("boom", info.message),
+        }
+    }
+
+    #[test]
+    fn ref_fn_try_catches_panic() {
+        let lambda = |_: ()| -> usize { panic!("boom") };
+        let fun: RRefFnTry<(), usize> = (&lambda).into();
+        match fun.call(()) {
+            RResult::ROk(_) => panic!("expected RErr"),
+            RResult::RErr(info) => assert_eq!("boom", info.message),
+        }
+    }
+
+    #[test]
+    fn ref_fn_send_across_thread() {
+        let lambda = |_| 5usize;
+        let fun: RRefFnSend<(), usize> = (&lambda).into();
+        let result = std::thread::scope(|scope| scope.spawn(move || fun.call(())).join().unwrap());
+        assert_eq!(5, result);
+    }
 }
@@ -1,4 +1,8 @@
 use std::marker::PhantomData;
+use std::panic::AssertUnwindSafe;
+
+use crate::allocator::RAllocator;
+use crate::rpanic::{RPanicInfo, RResult};
 
 pub trait RFnMut<TParam, TResult> {
     fn call(&mut self, p: TParam) -> TResult;
@@ -17,6 +21,10 @@ pub struct RRefFnMut<'a, TParam, TResult> {
     ptr: extern "C" fn(usize, TParam) -> TResult,
     inner: usize,
     p: PhantomData<&'a ()>,
+    // See `RRefFn::_not_send_sync` in `rfn.rs`: suppresses accidental auto-`Send`/
+    // `Sync` leaking through the erased `usize`/fn-pointer fields regardless of the
+    // captured closure.
+    _not_send_sync: PhantomData<*const ()>,
 }
 
 impl<'a, T, TParam, TResult> From<&'a mut T> for RRefFnMut<'a, TParam, TResult>
@@ -37,6 +45,7 @@ where
             ptr: ptr::<T, TParam, TResult>,
             inner,
             p: PhantomData,
+            _not_send_sync: PhantomData,
         }
     }
 }
@@ -51,25 +60,168 @@ impl<'a, TParam, TResult> RFnMut<TParam, TResult> for RRefFnMut<'a, TParam, TRes
 #[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
 pub struct RBoxFnMut<TParam, TResult> {
     caller: extern "C" fn(usize, TParam) -> TResult,
-    remover: extern "C" fn(usize),
+    remover: extern "C" fn(usize, RAllocator),
     inner: usize,
+    allocator: RAllocator,
+    // See `RRefFn::_not_send_sync` in `rfn.rs`.
+    _not_send_sync: PhantomData<*const ()>,
 }
 
-
 impl<TFn, TParam, TResult> From<TFn> for RBoxFnMut<TParam, TResult>
 where
     TFn: FnMut(TParam) -> TResult,
 {
     fn from(inner: TFn) -> Self {
+        Self::from_in(inner, RAllocator::GLOBAL)
+    }
+}
+
+extern "C" fn caller<TFn, TParam, TResult>(that: usize, param: TParam) -> TResult
+where
+    TFn: FnMut(TParam) -> TResult,
+{
+    (unsafe { &mut *(that as *mut TFn) })(param)
+}
+
+extern "C" fn dropper<TFn, TParam, TResult>(that: usize, allocator: RAllocator)
+where
+    TFn: FnMut(TParam) -> TResult,
+{
+    let ptr = that as *mut TFn;
+    unsafe {
+        std::ptr::drop_in_place(ptr);
+        // See the analogous check in `rfn.rs::dropper`: a ZST never went through
+        // `allocator.alloc`, so it must not go through `allocator.dealloc` either.
+        let layout = std::alloc::Layout::new::<TFn>();
+        if layout.size() != 0 {
+            (allocator.dealloc)(ptr as *mut u8, layout.size(), layout.align());
+        }
+    }
+}
+
+impl<TParam, TResult> RBoxFnMut<TParam, TResult> {
+    /// Builds an [`RBoxFnMut`] whose backing storage is allocated through `allocator`
+    /// instead of the global allocator, so allocation and deallocation always happen
+    /// on the same side of the FFI boundary regardless of which library runs `Drop`.
+    pub fn from_in<TFn>(inner: TFn, allocator: RAllocator) -> Self
+    where
+        TFn: FnMut(TParam) -> TResult,
+    {
+        let layout = std::alloc::Layout::new::<TFn>();
+        let ptr = if layout.size() == 0 {
+            std::ptr::NonNull::<TFn>::dangling().as_ptr()
+        } else {
+            let ptr = (allocator.alloc)(layout.size(), layout.align()) as *mut TFn;
+            assert!(!ptr.is_null(), "RAllocator::alloc returned a null pointer");
+            ptr
+        };
+        unsafe { ptr.write(inner) };
+
+        Self {
+            caller: caller::<TFn, TParam, TResult>,
+            remover: dropper::<TFn, TParam, TResult>,
+            inner: ptr as usize,
+            allocator,
+            _not_send_sync: PhantomData,
+        }
+    }
+}
+
+impl<'a, TParam, TResult> Drop for RBoxFnMut<TParam, TResult> {
+    fn drop(&mut self) {
+        (self.remover)(self.inner, self.allocator);
+    }
+}
+
+impl<TParam, TResult> RFnMut<TParam, TResult> for RBoxFnMut<TParam, TResult> {
+    fn call(&mut self, p: TParam) -> TResult {
+        (self.caller)(self.inner, p)
+    }
+}
+
+/// Thread-safe variant of [`RBoxFnMut`] for closures that may be sent to another
+/// thread, e.g. handed off to a thread pool or [`std::thread::spawn`]. Captures the
+/// `TFn: Send` bound at construction time, mirroring how `Arc<T>` only opts into
+/// `Send` when `T: Send`.
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
+pub struct RBoxFnMutSend<TParam, TResult>(RBoxFnMut<TParam, TResult>);
+
+impl<TFn, TParam, TResult> From<TFn> for RBoxFnMutSend<TParam, TResult>
+where
+    TFn: FnMut(TParam) -> TResult + Send,
+{
+    fn from(inner: TFn) -> Self {
+        Self(inner.into())
+    }
+}
+
+unsafe impl<TParam, TResult> Send for RBoxFnMutSend<TParam, TResult> {}
+
+impl<TParam, TResult> RFnMut<TParam, TResult> for RBoxFnMutSend<TParam, TResult> {
+    fn call(&mut self, p: TParam) -> TResult {
+        self.0.call(p)
+    }
+}
+
+/// Thread-safe variant of [`RRefFnMut`] for closures borrowed from a context that may
+/// be sent to another thread. Since the closure is held by exclusive reference, `Send`
+/// only requires the closure itself to be `Send`, matching `&mut T: Send`.
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
+pub struct RRefFnMutSend<'a, TParam, TResult>(RRefFnMut<'a, TParam, TResult>);
+
+impl<'a, T, TParam, TResult> From<&'a mut T> for RRefFnMutSend<'a, TParam, TResult>
+where
+    T: 'a + FnMut(TParam) -> TResult + Send,
+{
+    fn from(inner: &'a mut T) -> Self {
+        Self(inner.into())
+    }
+}
+
+unsafe impl<'a, TParam, TResult> Send for RRefFnMutSend<'a, TParam, TResult> {}
+
+impl<'a, TParam, TResult> RFnMut<TParam, TResult> for RRefFnMutSend<'a, TParam, TResult> {
+    fn call(&mut self, p: TParam) -> TResult {
+        self.0.call(p)
+    }
+}
+
+/// Panic-safe variant of [`RBoxFnMut`] whose trampoline catches any panic raised by
+/// the closure instead of letting it unwind across the `extern "C"` boundary.
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
+pub struct RBoxFnMutTry<TParam, TResult> {
+    caller: extern "C" fn(usize, TParam) -> RResult<TResult, RPanicInfo>,
+    remover: extern "C" fn(usize),
+    inner: usize,
+}
+
+impl<TFn, TParam, TResult> From<TFn> for RBoxFnMutTry<TParam, TResult>
+where
+    TFn: FnMut(TParam) -> TResult,
+{
+    fn from(inner: TFn) -> Self {
+        RPanicInfo::install_hook();
         let box_inner = Box::new(inner);
-        let inner = Box::into_raw(box_inner);
-        let inner = inner as usize;
+        let inner = Box::into_raw(box_inner) as usize;
 
-        extern "C" fn caller<TFn, TParam, TResult>(that: usize, param: TParam) -> TResult
+        // See the matching comment in `rfn.rs`: `RPanicInfo::message` is a plain
+        // `String` outside the `abi_stable` feature, which is fine for this
+        // intra-compilation `catch_unwind` trampoline.
+        #[allow(improper_ctypes_definitions)]
+        extern "C" fn caller<TFn, TParam, TResult>(
+            that: usize,
+            param: TParam,
+        ) -> RResult<TResult, RPanicInfo>
         where
             TFn: FnMut(TParam) -> TResult,
         {
-            (unsafe { &mut *(that as *mut TFn) })(param)
+            let function = unsafe { &mut *(that as *mut TFn) };
+            std::panic::catch_unwind(AssertUnwindSafe(|| function(param)))
+                .map_err(RPanicInfo::from_payload)
+                .into()
         }
         extern "C" fn dropper<TFn, TParam, TResult>(that: usize)
         where
@@ -85,18 +237,106 @@ where
     }
 }
 
-impl<'a, TParam, TResult> Drop for RBoxFnMut<TParam, TResult> {
+impl<TParam, TResult> Drop for RBoxFnMutTry<TParam, TResult> {
     fn drop(&mut self) {
         (self.remover)(self.inner);
     }
 }
 
-impl<TParam, TResult> RFnMut<TParam, TResult> for RBoxFnMut<TParam, TResult> {
-    fn call(&mut self, p: TParam) -> TResult {
+impl<TParam, TResult> RFnMut<TParam, RResult<TResult, RPanicInfo>> for RBoxFnMutTry<TParam, TResult> {
+    fn call(&mut self, p: TParam) -> RResult<TResult, RPanicInfo> {
         (self.caller)(self.inner, p)
     }
 }
 
+/// Panic-safe variant of [`RRefFnMut`] whose trampoline catches any panic raised by
+/// the closure instead of letting it unwind across the `extern "C"` boundary.
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
+pub struct RRefFnMutTry<'a, TParam, TResult> {
+    ptr: extern "C" fn(usize, TParam) -> RResult<TResult, RPanicInfo>,
+    inner: usize,
+    p: PhantomData<&'a ()>,
+}
+
+impl<'a, T, TParam, TResult> From<&'a mut T> for RRefFnMutTry<'a, TParam, TResult>
+where
+    T: 'a + FnMut(TParam) -> TResult,
+{
+    fn from(inner: &'a mut T) -> Self {
+        RPanicInfo::install_hook();
+        let inner: usize = unsafe { std::mem::transmute(inner) };
+
+        extern "C" fn ptr<T, TParam, TResult>(
+            inner: usize,
+            p: TParam,
+        ) -> RResult<TResult, RPanicInfo>
+        where
+            T: FnMut(TParam) -> TResult,
+        {
+            let function: &mut T = unsafe { std::mem::transmute(inner) };
+            std::panic::catch_unwind(AssertUnwindSafe(|| function(p)))
+                .map_err(RPanicInfo::from_payload)
+                .into()
+        }
+        Self {
+            ptr: ptr::<T, TParam, TResult>,
+            inner,
+            p: PhantomData,
+        }
+    }
+}
+
+impl<'a, TParam, TResult> RFnMut<TParam, RResult<TResult, RPanicInfo>>
+    for RRefFnMutTry<'a, TParam, TResult>
+{
+    fn call(&mut self, p: TParam) -> RResult<TResult, RPanicInfo> {
+        (self.ptr)(self.inner, p)
+    }
+}
+
+// See the analogous `impl_rbox_fn_arity!` in `rfn.rs`: the existing blanket `From`
+// impl above already covers arity 1, so this only fills in 0 and 2..=16. Each arity
+// gets its own method name rather than one overloaded `from_fn`, since an inherent
+// method of the same name repeated across impls on different tuple shapes is
+// ambiguous to rustc even when the call site's target type is fully known.
+macro_rules! impl_rbox_fn_mut_arity {
+    ($($name:ident => ($($arg:ident),*)),+ $(,)?) => {
+        $(
+            impl<$($arg,)* TResult> RBoxFnMut<($($arg,)*), TResult> {
+                /// Builds an [`RBoxFnMut`] from an ordinary closure of this arity,
+                /// tupling its arguments at the FFI seam.
+                pub fn $name<TFn>(mut inner: TFn) -> Self
+                where
+                    TFn: FnMut($($arg),*) -> TResult,
+                {
+                    #[allow(non_snake_case)]
+                    RBoxFnMut::from(move |($($arg,)*): ($($arg,)*)| inner($($arg),*))
+                }
+            }
+        )+
+    };
+}
+
+impl_rbox_fn_mut_arity!(
+    from_fn0 => (),
+    from_fn2 => (A, B),
+    from_fn3 => (A, B, C),
+    from_fn4 => (A, B, C, D),
+    from_fn5 => (A, B, C, D, E),
+    from_fn6 => (A, B, C, D, E, F),
+    from_fn7 => (A, B, C, D, E, F, G),
+    from_fn8 => (A, B, C, D, E, F, G, H),
+    from_fn9 => (A, B, C, D, E, F, G, H, I),
+    from_fn10 => (A, B, C, D, E, F, G, H, I, J),
+    from_fn11 => (A, B, C, D, E, F, G, H, I, J, K),
+    from_fn12 => (A, B, C, D, E, F, G, H, I, J, K, L),
+    from_fn13 => (A, B, C, D, E, F, G, H, I, J, K, L, M),
+    from_fn14 => (A, B, C, D, E, F, G, H, I, J, K, L, M, N),
+    from_fn15 => (A, B, C, D, E, F, G, H, I, J, K, L, M, N, O),
+    from_fn16 => (A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P),
+);
+
 #[cfg(test)]
 mod tests {
     use std::sync::atomic::{AtomicU8, Ordering};
@@ -176,4 +416,99 @@ mod tests {
         drop(closure);
         assert_eq!(42, DROP_EVENT.load(Ordering::SeqCst));
     }
+
+    #[test]
+    fn box_fn_mut_send_across_thread() {
+        let mut count = 0;
+        let owned = "Value".to_owned();
+        let mut fun: RBoxFnMutSend<(), usize> = (move |_| {
+            count += 1;
+            owned.len() + count
+        })
+        .into();
+        let result = std::thread::spawn(move || fun.call(())).join().unwrap();
+        assert_eq!(6, result);
+    }
+
+    #[test]
+    fn box_fn_mut_from_in_uses_given_allocator() {
+        static ALLOC_COUNT: AtomicU8 = AtomicU8::new(0);
+        static DEALLOC_COUNT: AtomicU8 = AtomicU8::new(0);
+
+        extern "C" fn alloc(size: usize, align: usize) -> *mut u8 {
+            ALLOC_COUNT.fetch_add(1, Ordering::SeqCst);
+            unsafe { std::alloc::alloc(std::alloc::Layout::from_size_align_unchecked(size, align)) }
+        }
+        extern "C" fn dealloc(ptr: *mut u8, size: usize, align: usize) {
+            DEALLOC_COUNT.fetch_add(1, Ordering::SeqCst);
+            unsafe {
+                std::alloc::dealloc(ptr, std::alloc::Layout::from_size_align_unchecked(size, align))
+            }
+        }
+
+        let mut count = 0;
+        let mut fun = RBoxFnMut::from_in(
+            move |_: ()| {
+                count += 1;
+                count
+            },
+            RAllocator { alloc, dealloc },
+        );
+        assert_eq!(1, ALLOC_COUNT.load(Ordering::SeqCst));
+        assert_eq!(1, fun.call(()));
+        drop(fun);
+        assert_eq!(1, DEALLOC_COUNT.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn box_fn_mut_from_fn_arity_zero() {
+        let mut count = 0;
+        let mut fun: RBoxFnMut<(), usize> = RBoxFnMut::from_fn0(move || {
+            count += 1;
+            count
+        });
+        assert_eq!(1, fun.call(()));
+        assert_eq!(2, fun.call(()));
+    }
+
+    #[test]
+    fn box_fn_mut_from_fn_arity_two() {
+        let mut total = 0;
+        let mut fun: RBoxFnMut<(usize, usize), usize> = RBoxFnMut::from_fn2(move |a, b| {
+            total += a + b;
+            total
+        });
+        assert_eq!(3, fun.call((1, 2)));
+        assert_eq!(9, fun.call((2, 4)));
+    }
+
+    #[test]
+    fn box_fn_mut_try_catches_panic() {
+        let mut fun: RBoxFnMutTry<(), usize> = (|_| panic!("boom")).into();
+        match fun.call(()) {
+            RResult::ROk(_) => panic!("expected RErr"),
+            RResult::RErr(info) => assert_eq!("boom", info.message),
+        }
+    }
+
+    #[test]
+    fn ref_fn_mut_try_catches_panic() {
+        let mut lambda = |_: ()| -> usize { panic!("boom") };
+        let mut fun: RRefFnMutTry<(), usize> = (&mut lambda).into();
+        match fun.call(()) {
+            RResult::ROk(_) => panic!("expected RErr"),
+            RResult::RErr(info) => assert_eq!("boom", info.message),
+        }
+    }
+
+    #[test]
+    fn ref_fn_mut_send_across_thread() {
+        let mut lambda = |_| 5usize;
+        let fun: RRefFnMutSend<(), usize> = (&mut lambda).into();
+        let result = std::thread::scope(|scope| {
+            let mut fun = fun;
+            scope.spawn(move || fun.call(())).join().unwrap()
+        });
+        assert_eq!(5, result);
+    }
 }
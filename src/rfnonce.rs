@@ -1,3 +1,9 @@
+use std::marker::PhantomData;
+use std::panic::AssertUnwindSafe;
+
+use crate::allocator::RAllocator;
+use crate::rpanic::{RPanicInfo, RResult};
+
 pub trait RFnOnce<TParam, TResult> {
     fn call(self, p: TParam) -> TResult;
 }
@@ -12,9 +18,12 @@ impl<TFn: FnOnce(TParam) -> TResult, TParam, TResult> RFnOnce<TParam, TResult> f
 #[repr(C)]
 #[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
 pub struct RBoxFnOnce<TParam, TResult> {
-    caller: extern "C" fn(usize, TParam) -> TResult,
-    remover: extern "C" fn(usize),
+    caller: extern "C" fn(usize, TParam, RAllocator) -> TResult,
+    remover: extern "C" fn(usize, RAllocator),
     inner: usize,
+    allocator: RAllocator,
+    // See `RRefFn::_not_send_sync` in `rfn.rs`.
+    _not_send_sync: PhantomData<*const ()>,
 }
 
 impl<T, TParam, TResult> From<T> for RBoxFnOnce<TParam, TResult>
@@ -22,15 +31,146 @@ where
     T: FnOnce(TParam) -> TResult,
 {
     fn from(inner: T) -> Self {
-        let box_inner = Box::new(inner);
-        let inner: usize = Box::into_raw(box_inner) as usize;
+        Self::from_in(inner, RAllocator::GLOBAL)
+    }
+}
+
+extern "C" fn caller<T, TParam, TResult>(
+    that: usize,
+    param: TParam,
+    allocator: RAllocator,
+) -> TResult
+where
+    T: FnOnce(TParam) -> TResult,
+{
+    let ptr = that as *mut T;
+    let function = unsafe { ptr.read() };
+    // See the analogous check in `rfn.rs::dropper`: a ZST never went through
+    // `allocator.alloc`, so it must not go through `allocator.dealloc` either.
+    let layout = std::alloc::Layout::new::<T>();
+    if layout.size() != 0 {
+        (allocator.dealloc)(ptr as *mut u8, layout.size(), layout.align());
+    }
+    function(param)
+}
+
+extern "C" fn dropper<T, TParam, TResult>(that: usize, allocator: RAllocator)
+where
+    T: FnOnce(TParam) -> TResult,
+{
+    let ptr = that as *mut T;
+    unsafe {
+        std::ptr::drop_in_place(ptr);
+        let layout = std::alloc::Layout::new::<T>();
+        if layout.size() != 0 {
+            (allocator.dealloc)(ptr as *mut u8, layout.size(), layout.align());
+        }
+    }
+}
+
+impl<TParam, TResult> RBoxFnOnce<TParam, TResult> {
+    /// Builds an [`RBoxFnOnce`] whose backing storage is allocated through
+    /// `allocator` instead of the global allocator, so allocation and deallocation
+    /// always happen on the same side of the FFI boundary regardless of which
+    /// library runs `Drop`.
+    pub fn from_in<T>(inner: T, allocator: RAllocator) -> Self
+    where
+        T: FnOnce(TParam) -> TResult,
+    {
+        let layout = std::alloc::Layout::new::<T>();
+        let ptr = if layout.size() == 0 {
+            std::ptr::NonNull::<T>::dangling().as_ptr()
+        } else {
+            let ptr = (allocator.alloc)(layout.size(), layout.align()) as *mut T;
+            assert!(!ptr.is_null(), "RAllocator::alloc returned a null pointer");
+            ptr
+        };
+        unsafe { ptr.write(inner) };
+
+        Self {
+            caller: caller::<T, TParam, TResult>,
+            remover: dropper::<T, TParam, TResult>,
+            inner: ptr as usize,
+            allocator,
+            _not_send_sync: PhantomData,
+        }
+    }
+}
+
+impl<TParam, TResult> Drop for RBoxFnOnce<TParam, TResult> {
+    fn drop(&mut self) {
+        if self.inner != 0 {
+            (self.remover)(self.inner, self.allocator);
+        }
+    }
+}
+impl<TParam, TResult> RFnOnce<TParam, TResult> for RBoxFnOnce<TParam, TResult> {
+    fn call(mut self, p: TParam) -> TResult {
+        let inner = self.inner;
+        let allocator = self.allocator;
+        self.inner = 0;
+        (self.caller)(inner, p, allocator)
+    }
+}
+
+/// Thread-safe variant of [`RBoxFnOnce`] for closures that may be sent to another
+/// thread, e.g. handed off to a thread pool or [`std::thread::spawn`]. Captures the
+/// `TFn: Send` bound at construction time, mirroring how `Arc<T>` only opts into
+/// `Send` when `T: Send`.
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
+pub struct RBoxFnOnceSend<TParam, TResult>(RBoxFnOnce<TParam, TResult>);
+
+impl<T, TParam, TResult> From<T> for RBoxFnOnceSend<TParam, TResult>
+where
+    T: FnOnce(TParam) -> TResult + Send,
+{
+    fn from(inner: T) -> Self {
+        Self(inner.into())
+    }
+}
+
+unsafe impl<TParam, TResult> Send for RBoxFnOnceSend<TParam, TResult> {}
+
+impl<TParam, TResult> RFnOnce<TParam, TResult> for RBoxFnOnceSend<TParam, TResult> {
+    fn call(self, p: TParam) -> TResult {
+        self.0.call(p)
+    }
+}
+
+/// Panic-safe variant of [`RBoxFnOnce`] whose trampoline catches any panic raised by
+/// the closure instead of letting it unwind across the `extern "C"` boundary.
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
+pub struct RBoxFnOnceTry<TParam, TResult> {
+    caller: extern "C" fn(usize, TParam) -> RResult<TResult, RPanicInfo>,
+    remover: extern "C" fn(usize),
+    inner: usize,
+}
+
+impl<T, TParam, TResult> From<T> for RBoxFnOnceTry<TParam, TResult>
+where
+    T: FnOnce(TParam) -> TResult,
+{
+    fn from(inner: T) -> Self {
+        RPanicInfo::install_hook();
+        let inner: usize = Box::into_raw(Box::new(inner)) as usize;
 
-        extern "C" fn caller<T, TParam, TResult>(that: usize, param: TParam) -> TResult
+        // See the matching comment in `rfn.rs`: `RPanicInfo::message` is a plain
+        // `String` outside the `abi_stable` feature, which is fine for this
+        // intra-compilation `catch_unwind` trampoline.
+        #[allow(improper_ctypes_definitions)]
+        extern "C" fn caller<T, TParam, TResult>(
+            that: usize,
+            param: TParam,
+        ) -> RResult<TResult, RPanicInfo>
         where
             T: FnOnce(TParam) -> TResult,
         {
             let function = unsafe { Box::from_raw(that as *mut T) };
-            (function)(param)
+            std::panic::catch_unwind(AssertUnwindSafe(|| function(param)))
+                .map_err(RPanicInfo::from_payload)
+                .into()
         }
         extern "C" fn dropper<T, TParam, TResult>(that: usize)
         where
@@ -46,21 +186,66 @@ where
     }
 }
 
-impl<TParam, TResult> Drop for RBoxFnOnce<TParam, TResult> {
+impl<TParam, TResult> Drop for RBoxFnOnceTry<TParam, TResult> {
     fn drop(&mut self) {
         if self.inner != 0 {
             (self.remover)(self.inner);
         }
     }
 }
-impl<TParam, TResult> RFnOnce<TParam, TResult> for RBoxFnOnce<TParam, TResult> {
-    fn call(mut self, p: TParam) -> TResult {
+
+impl<TParam, TResult> RFnOnce<TParam, RResult<TResult, RPanicInfo>>
+    for RBoxFnOnceTry<TParam, TResult>
+{
+    fn call(mut self, p: TParam) -> RResult<TResult, RPanicInfo> {
         let inner = self.inner;
         self.inner = 0;
         (self.caller)(inner, p)
     }
 }
 
+// See the analogous `impl_rbox_fn_arity!` in `rfn.rs`: the existing blanket `From`
+// impl above already covers arity 1, so this only fills in 0 and 2..=16. Each arity
+// gets its own method name rather than one overloaded `from_fn`, since an inherent
+// method of the same name repeated across impls on different tuple shapes is
+// ambiguous to rustc even when the call site's target type is fully known.
+macro_rules! impl_rbox_fn_once_arity {
+    ($($name:ident => ($($arg:ident),*)),+ $(,)?) => {
+        $(
+            impl<$($arg,)* TResult> RBoxFnOnce<($($arg,)*), TResult> {
+                /// Builds an [`RBoxFnOnce`] from an ordinary closure of this arity,
+                /// tupling its arguments at the FFI seam.
+                pub fn $name<TFn>(inner: TFn) -> Self
+                where
+                    TFn: FnOnce($($arg),*) -> TResult,
+                {
+                    #[allow(non_snake_case)]
+                    RBoxFnOnce::from(move |($($arg,)*): ($($arg,)*)| inner($($arg),*))
+                }
+            }
+        )+
+    };
+}
+
+impl_rbox_fn_once_arity!(
+    from_fn0 => (),
+    from_fn2 => (A, B),
+    from_fn3 => (A, B, C),
+    from_fn4 => (A, B, C, D),
+    from_fn5 => (A, B, C, D, E),
+    from_fn6 => (A, B, C, D, E, F),
+    from_fn7 => (A, B, C, D, E, F, G),
+    from_fn8 => (A, B, C, D, E, F, G, H),
+    from_fn9 => (A, B, C, D, E, F, G, H, I),
+    from_fn10 => (A, B, C, D, E, F, G, H, I, J),
+    from_fn11 => (A, B, C, D, E, F, G, H, I, J, K),
+    from_fn12 => (A, B, C, D, E, F, G, H, I, J, K, L),
+    from_fn13 => (A, B, C, D, E, F, G, H, I, J, K, L, M),
+    from_fn14 => (A, B, C, D, E, F, G, H, I, J, K, L, M, N),
+    from_fn15 => (A, B, C, D, E, F, G, H, I, J, K, L, M, N, O),
+    from_fn16 => (A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P),
+);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -108,4 +293,68 @@ mod tests {
         }));
         assert_eq!(42, DROP_EVENT.load(Ordering::SeqCst));
     }
+
+    #[test]
+    fn box_fn_once_from_in_uses_given_allocator() {
+        static ALLOC_COUNT: AtomicU8 = AtomicU8::new(0);
+        static DEALLOC_COUNT: AtomicU8 = AtomicU8::new(0);
+
+        extern "C" fn alloc(size: usize, align: usize) -> *mut u8 {
+            ALLOC_COUNT.fetch_add(1, Ordering::SeqCst);
+            unsafe { std::alloc::alloc(std::alloc::Layout::from_size_align_unchecked(size, align)) }
+        }
+        extern "C" fn dealloc(ptr: *mut u8, size: usize, align: usize) {
+            DEALLOC_COUNT.fetch_add(1, Ordering::SeqCst);
+            unsafe {
+                std::alloc::dealloc(ptr, std::alloc::Layout::from_size_align_unchecked(size, align))
+            }
+        }
+
+        let owned = "Value".to_owned();
+        let fun = RBoxFnOnce::from_in(move |_: ()| owned.len(), RAllocator { alloc, dealloc });
+        assert_eq!(1, ALLOC_COUNT.load(Ordering::SeqCst));
+        assert_eq!(5, fun.call(()));
+        assert_eq!(1, DEALLOC_COUNT.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn box_fn_once_from_fn_arity_zero() {
+        let owned = "Value".to_owned();
+        let fun: RBoxFnOnce<(), usize> = RBoxFnOnce::from_fn0(move || owned.len());
+        assert_eq!(5, fun.call(()));
+    }
+
+    #[test]
+    fn box_fn_once_from_fn_arity_two() {
+        let suffix = "!".to_owned();
+        let fun: RBoxFnOnce<(&str, &str), String> =
+            RBoxFnOnce::from_fn2(move |a: &str, b: &str| format!("{a}{b}{suffix}"));
+        assert_eq!("ab!".to_string(), fun.call(("a", "b")));
+    }
+
+    #[test]
+    fn box_fn_once_try_catches_panic() {
+        let fun: RBoxFnOnceTry<(), usize> = (|_| -> usize { panic!("boom") }).into();
+        match fun.call(()) {
+            RResult::ROk(_) => panic!("expected RErr"),
+            RResult::RErr(info) => assert_eq!("boom", info.message),
+        }
+    }
+
+    #[test]
+    fn box_fn_once_try_returns_ok_on_success() {
+        let fun: RBoxFnOnceTry<(), usize> = (|_| 5usize).into();
+        match fun.call(()) {
+            RResult::ROk(v) => assert_eq!(5, v),
+            RResult::RErr(_) => panic!("expected ROk"),
+        }
+    }
+
+    #[test]
+    fn box_fn_once_send_across_thread() {
+        let owned = "Value".to_owned();
+        let fun: RBoxFnOnceSend<(), usize> = (move |_| owned.len()).into();
+        let result = std::thread::spawn(move || fun.call(())).join().unwrap();
+        assert_eq!(5, result);
+    }
 }
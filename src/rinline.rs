@@ -0,0 +1,193 @@
+use std::mem::{align_of, size_of};
+
+use crate::rfn::RFn;
+
+// Plain `[u8; N]` rather than `[MaybeUninit<u8>; N]`: `MaybeUninit` has no `StableAbi`
+// impl, and a zero-initialized byte array is the form `abi_stable` already knows how
+// to derive layout info for. Note that `abi_stable`'s `StableAbi` derive has historically
+// had limited support for `const` generic parameters (as opposed to type parameters);
+// this should be re-checked against the pinned `abi_stable` version before relying on
+// it in a release build.
+/// Fixed-size, suitably-aligned byte buffer backing [`RInlineFn`]'s inline storage.
+/// `repr(C, align(8))` covers the common case of closures that capture only pointers,
+/// `usize`-sized integers or `f64`s without heap allocation.
+#[repr(C, align(8))]
+#[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
+struct InlineBuf<const N: usize>([u8; N]);
+
+impl<const N: usize> InlineBuf<N> {
+    fn zeroed() -> Self {
+        Self([0u8; N])
+    }
+}
+
+// Only a `Fn`-based variant is provided for now: an `RInlineFnMut`/`RInlineFnOnce`
+// would need their own inline-vs-heap dispatch plumbed through `RBoxFnMut`/
+// `RBoxFnOnce`'s `&mut`/by-value call conventions, which is straightforward but
+// additional surface area this request doesn't cover; add them if a caller needs
+// allocation-free storage for a `FnMut`/`FnOnce`.
+/// FFI-Safe lambda that stores the closure inline in a fixed-size buffer when it fits,
+/// avoiding the heap allocation [`RBoxFn`](crate::RBoxFn) makes for any non-zero-sized
+/// closure. Closures
+/// too large (`size_of::<TFn>() > N`) or over-aligned for the buffer fall back
+/// transparently to the same boxed storage `RBoxFn` uses.
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
+pub struct RInlineFn<TParam, TResult, const N: usize> {
+    caller: extern "C" fn(usize, TParam) -> TResult,
+    remover: extern "C" fn(usize),
+    is_inline: bool,
+    inline: InlineBuf<N>,
+    heap: usize,
+}
+
+impl<TFn, TParam, TResult, const N: usize> From<TFn> for RInlineFn<TParam, TResult, N>
+where
+    TFn: Fn(TParam) -> TResult,
+{
+    fn from(inner: TFn) -> Self {
+        extern "C" fn caller<TFn, TParam, TResult>(that: usize, param: TParam) -> TResult
+        where
+            TFn: Fn(TParam) -> TResult,
+        {
+            (unsafe { &*(that as *mut TFn) })(param)
+        }
+        extern "C" fn inline_dropper<TFn, TParam, TResult>(that: usize)
+        where
+            TFn: Fn(TParam) -> TResult,
+        {
+            unsafe { std::ptr::drop_in_place(that as *mut TFn) };
+        }
+        extern "C" fn heap_dropper<TFn, TParam, TResult>(that: usize)
+        where
+            TFn: Fn(TParam) -> TResult,
+        {
+            drop(unsafe { Box::from_raw(that as *mut TFn) });
+        }
+
+        if size_of::<TFn>() <= N && align_of::<TFn>() <= align_of::<InlineBuf<N>>() {
+            let mut inline = InlineBuf::<N>::zeroed();
+            unsafe { (inline.0.as_mut_ptr() as *mut TFn).write(inner) };
+            Self {
+                caller: caller::<TFn, TParam, TResult>,
+                remover: inline_dropper::<TFn, TParam, TResult>,
+                is_inline: true,
+                inline,
+                heap: 0,
+            }
+        } else {
+            let heap = Box::into_raw(Box::new(inner)) as usize;
+            Self {
+                caller: caller::<TFn, TParam, TResult>,
+                remover: heap_dropper::<TFn, TParam, TResult>,
+                is_inline: false,
+                inline: InlineBuf::<N>::zeroed(),
+                heap,
+            }
+        }
+    }
+}
+
+impl<TParam, TResult, const N: usize> RInlineFn<TParam, TResult, N> {
+    fn address(&self) -> usize {
+        if self.is_inline {
+            self.inline.0.as_ptr() as usize
+        } else {
+            self.heap
+        }
+    }
+}
+
+impl<TParam, TResult, const N: usize> Drop for RInlineFn<TParam, TResult, N> {
+    fn drop(&mut self) {
+        let address = self.address();
+        (self.remover)(address);
+    }
+}
+
+impl<'a, TParam, TResult: 'a, const N: usize> RFn<'a, TParam, TResult>
+    for RInlineFn<TParam, TResult, N>
+{
+    fn call(&'a self, p: TParam) -> TResult {
+        (self.caller)(self.address(), p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU8, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn stores_small_closure_inline() {
+        let fun: RInlineFn<(), usize, 16> = (|_| 5usize).into();
+        assert_eq!(5, fun.call(()));
+    }
+
+    #[test]
+    fn falls_back_to_boxed_storage_when_too_large() {
+        let big = [0u8; 64];
+        let fun: RInlineFn<(), usize, 16> = (move |_| big.len()).into();
+        assert_eq!(64, fun.call(()));
+    }
+
+    #[test]
+    fn move_value() {
+        fn return_inline(a: String) -> RInlineFn<(), usize, 32> {
+            (move |_| a.len()).into()
+        }
+        let inline = return_inline("foo".to_owned());
+        assert_eq!(3, inline.call(()));
+    }
+
+    #[test]
+    fn drop_inline_fn_runs_destructor_exactly_once() {
+        static DROP_EVENT: AtomicU8 = AtomicU8::new(0);
+        struct Foo(u8);
+        impl Foo {
+            fn bar(&self) -> u8 {
+                self.0
+            }
+        }
+        impl Drop for Foo {
+            fn drop(&mut self) {
+                // Accumulate rather than store, so a second drop would show up as 84.
+                DROP_EVENT.fetch_add(self.0, Ordering::SeqCst);
+            }
+        }
+        fn return_inline(a: Foo) -> RInlineFn<(), usize, 16> {
+            (move |_| a.bar() as usize).into()
+        }
+
+        let inline = return_inline(Foo(42));
+        assert_eq!(0, DROP_EVENT.load(Ordering::SeqCst));
+        drop(inline);
+        assert_eq!(42, DROP_EVENT.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn drop_boxed_fallback_runs_destructor_exactly_once() {
+        static DROP_EVENT: AtomicU8 = AtomicU8::new(0);
+        struct Foo([u8; 64]);
+        impl Foo {
+            fn bar(&self) -> usize {
+                self.0.len()
+            }
+        }
+        impl Drop for Foo {
+            fn drop(&mut self) {
+                // Accumulate rather than store, so a second drop would show up as 84.
+                DROP_EVENT.fetch_add(self.0[0], Ordering::SeqCst);
+            }
+        }
+        fn return_inline(a: Foo) -> RInlineFn<(), usize, 8> {
+            (move |_| a.bar()).into()
+        }
+
+        let inline = return_inline(Foo([42; 64]));
+        assert_eq!(0, DROP_EVENT.load(Ordering::SeqCst));
+        drop(inline);
+        assert_eq!(42, DROP_EVENT.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,120 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::sync::Once;
+
+/// FFI-safe stand-in for [`std::result::Result`], returned by the `Try` lambda
+/// families so a caught panic can cross the FFI boundary as data instead of as an
+/// unwind.
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
+pub enum RResult<T, E> {
+    ROk(T),
+    RErr(E),
+}
+
+impl<T, E> From<Result<T, E>> for RResult<T, E> {
+    fn from(result: Result<T, E>) -> Self {
+        match result {
+            Ok(t) => RResult::ROk(t),
+            Err(e) => RResult::RErr(e),
+        }
+    }
+}
+
+impl<T, E> From<RResult<T, E>> for Result<T, E> {
+    fn from(result: RResult<T, E>) -> Self {
+        match result {
+            RResult::ROk(t) => Ok(t),
+            RResult::RErr(e) => Err(e),
+        }
+    }
+}
+
+// Plain `String`/`Option<String>` aren't FFI-safe (no stable layout, and `String`
+// doesn't implement `StableAbi`), so `RPanicInfo` swaps in `abi_stable`'s owned string
+// and option types when that feature is enabled, mirroring every other repr(C) type
+// in this crate.
+#[cfg(feature = "abi_stable")]
+type RPanicString = abi_stable::std_types::RString;
+#[cfg(not(feature = "abi_stable"))]
+type RPanicString = String;
+
+#[cfg(feature = "abi_stable")]
+type RPanicLocation = abi_stable::std_types::ROption<RPanicString>;
+#[cfg(not(feature = "abi_stable"))]
+type RPanicLocation = Option<String>;
+
+/// FFI-safe description of a panic caught at the boundary by a `Try` lambda.
+///
+/// `location` is only populated when the panicking thread's hook was installed by
+/// this crate (see [`RPanicInfo::from_payload`]); it is `None` if the panic occurred
+/// before installation or the hook was overridden by other code.
+#[repr(C)]
+#[cfg_attr(feature = "abi_stable", derive(abi_stable::StableAbi))]
+pub struct RPanicInfo {
+    pub message: RPanicString,
+    pub location: RPanicLocation,
+}
+
+thread_local! {
+    static LAST_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+static INSTALL_HOOK: Once = Once::new();
+
+fn install_location_hook() {
+    INSTALL_HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let location = info.location().map(ToString::to_string);
+            LAST_LOCATION.with(|cell| *cell.borrow_mut() = location);
+            previous(info);
+        }));
+    });
+}
+
+impl RPanicInfo {
+    /// Builds an [`RPanicInfo`] from a [`catch_unwind`](std::panic::catch_unwind)
+    /// payload, extracting a message from the common `&str`/`String` panic payloads
+    /// and the location captured by this crate's panic hook, if any.
+    pub fn from_payload(payload: Box<dyn Any + Send>) -> Self {
+        let message = if let Some(s) = payload.downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = payload.downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "Box<dyn Any>".to_string()
+        };
+        let location = LAST_LOCATION.with(|cell| cell.borrow_mut().take());
+        Self {
+            message: build_message(message),
+            location: build_location(location),
+        }
+    }
+
+    pub(crate) fn install_hook() {
+        install_location_hook();
+    }
+}
+
+// Plain `.into()`/`RPanicString::from(..)` would be a no-op conversion (and trip
+// `clippy::useless_conversion`) when `RPanicString`/`RPanicLocation` alias `String`/
+// `Option<String>` outside the `abi_stable` feature, so the actual conversion is
+// feature-gated here instead.
+#[cfg(feature = "abi_stable")]
+fn build_message(message: String) -> RPanicString {
+    message.into()
+}
+#[cfg(not(feature = "abi_stable"))]
+fn build_message(message: String) -> RPanicString {
+    message
+}
+
+#[cfg(feature = "abi_stable")]
+fn build_location(location: Option<String>) -> RPanicLocation {
+    location.map(RPanicString::from).into()
+}
+#[cfg(not(feature = "abi_stable"))]
+fn build_location(location: Option<String>) -> RPanicLocation {
+    location
+}